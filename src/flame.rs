@@ -0,0 +1,125 @@
+//! The iterated-function-system engine at the core of the flame renderer:
+//! recurses down a tree of affine `Generator`s to a requested depth, and
+//! (via `process_levels_styled`) threads a running color index and
+//! variation-weight blend along each path the way the classic flame
+//! algorithm blends color over the chaos game, rather than tagging each
+//! transform with a fixed, unblended value.
+
+use std::rc::Rc;
+
+use nalgebra::Affine2;
+
+use crate::geometry::Rect;
+
+/// One affine generator plus the color index and nonlinear variation-weight
+/// blend the flame algorithm associates with it (see `mesh::Instance` for
+/// how these end up on the GPU).
+#[derive(Clone, Debug)]
+pub struct Generator {
+    pub mat: Affine2<f64>,
+    pub color: f64,
+    pub variation_weights: [f64; 4],
+}
+
+/// A node in the IFS recursion: `mat` is the transform composed from the
+/// root down to this point, `color`/`variation_weights` are the running
+/// blends `process_levels_styled` maintains alongside it.
+#[derive(Clone, Debug)]
+pub struct State {
+    pub mat: Affine2<f64>,
+    color: f64,
+    variation_weights: [f64; 4],
+    generators: Rc<[Generator]>,
+    bounds: Rect,
+}
+
+/// Implemented by whatever `get_state`'s cursor lookup returns: something
+/// that can hand back the root `State` of the IFS it represents.
+pub trait BoundedState {
+    fn get_state(&self) -> State;
+}
+
+impl State {
+    pub fn new(mat: Affine2<f64>, generators: Vec<Generator>, bounds: Rect) -> Self {
+        State {
+            mat,
+            color: 0.5,
+            variation_weights: [1.0, 0.0, 0.0, 0.0],
+            generators: generators.into(),
+            bounds,
+        }
+    }
+
+    pub fn get_bounds(&self) -> Rect {
+        self.bounds
+    }
+
+    /// The raw, unblended generators this state recurses into — distinct from
+    /// any particular depth's blended `color`/`variation_weights`, which only
+    /// exist on a `State` reached by walking them. Used by GPU compute
+    /// expansion, which needs the true per-generator operand to compose
+    /// against at every level rather than a pre-blended leaf.
+    pub fn generators(&self) -> &[Generator] {
+        &self.generators
+    }
+
+    /// Returns a copy of this state recursing into `generators` instead of
+    /// its own, keeping the current transform/color/bounds as the new root.
+    /// Used to splice a live-edited generator set in before walking the tree.
+    pub fn with_generators(&self, generators: Vec<Generator>) -> Self {
+        State {
+            generators: generators.into(),
+            ..self.clone()
+        }
+    }
+
+    /// Recurses exactly `levels` generator applications deep, calling
+    /// `visit` once per leaf with the fully composed transform at that leaf.
+    /// `levels == 0` visits the state itself. Used for mesh geometry, where
+    /// only the transform matters.
+    pub fn process_levels(&self, levels: usize, visit: &mut impl FnMut(&State)) {
+        if levels == 0 {
+            visit(self);
+            return;
+        }
+        for generator in self.generators.iter() {
+            let child = State {
+                mat: self.mat * generator.mat,
+                ..self.clone()
+            };
+            child.process_levels(levels - 1, visit);
+        }
+    }
+
+    /// Same recursion as `process_levels`, additionally threading a running
+    /// color index and variation-weight blend down each path: every step
+    /// blends the parent's running value with the generator's own via
+    /// `c = (c + c_i) * 0.5`, mirroring the GPU compute-expansion `compose`
+    /// in `wgpu_render::compute_expand`/`shaders/expand.wgsl`.
+    pub fn process_levels_styled(
+        &self,
+        levels: usize,
+        visit: &mut impl FnMut(&State, f64, [f64; 4]),
+    ) {
+        if levels == 0 {
+            visit(self, self.color, self.variation_weights);
+            return;
+        }
+        for generator in self.generators.iter() {
+            let mut variation_weights = [0.0; 4];
+            for (w, (parent, own)) in variation_weights
+                .iter_mut()
+                .zip(self.variation_weights.iter().zip(generator.variation_weights.iter()))
+            {
+                *w = (parent + own) * 0.5;
+            }
+            let child = State {
+                mat: self.mat * generator.mat,
+                color: (self.color + generator.color) * 0.5,
+                variation_weights,
+                ..self.clone()
+            };
+            child.process_levels_styled(levels - 1, visit);
+        }
+    }
+}
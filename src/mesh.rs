@@ -18,30 +18,70 @@ pub type TextureCoordinate = [f32; 2];
 
 pub type Position = [f32; 2];
 
+impl Vertex {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 2] =
+        wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x2];
+
+    pub fn layout<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
 #[repr(C)]
 #[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct Instance {
     row0: [f32; 4],
     row1: [f32; 4],
+    /// Running color index blended along the IFS path (`x` only), padded out
+    /// to a full vec4 so the struct stays a uniform 4-vec4 stride.
+    color: [f32; 4],
+    /// Weighted blend of the nonlinear variation functions (`linear`,
+    /// `sinusoidal`, `spherical`, `swirl`, one weight per slot, matching
+    /// `variations.wgsl`) applied to this instance after the affine transform
+    /// above. Blended along the IFS path the same way as `color`, so a
+    /// transform's curl fades in with depth instead of snapping on only at
+    /// its own generator.
+    variation_weights: [f32; 4],
+}
+
+impl Instance {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 4] = wgpu::vertex_attr_array![
+        2 => Float32x4, 3 => Float32x4, 4 => Float32x4, 5 => Float32x4
+    ];
+
+    pub fn layout<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Instance>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+/// Splices `scene.generators` (as fed by `imgui_overlay::EditorState::apply_to`)
+/// in over `state`'s own generator set, when the overlay has actually set any.
+/// Otherwise leaves `state` walking whatever `get_state`'s cursor lookup gave it.
+fn with_scene_generators(state: State, scene: &SceneState) -> State {
+    if scene.generators.is_empty() {
+        state
+    } else {
+        state.with_generators(scene.generators.clone())
+    }
 }
 
 pub fn build_mesh(scene: &SceneState) -> (Vec<Vertex>, Vec<Instance>) {
     let root = get_state([scene.cursor.x + 1.0, scene.cursor.y + 1.0], [2.0, 2.0]);
-    let state = root.get_state();
+    let state = with_scene_generators(root.get_state(), scene);
     let bounds = state.get_bounds();
-    let root_mat = geometry::letter_box(
-        geometry::Rect {
-            min: na::Point2::new(-1.0, -1.0),
-            max: na::Point2::new(1.0, 1.0),
-        },
-        bounds,
-    );
 
+    let tessellation = scene.tessellation.max(1);
     let corners = bounds.corners();
     let mut positions: Vec<Position> = vec![];
-    let tri_verts = [
-        corners[0], corners[1], corners[2], corners[0], corners[2], corners[3],
-    ];
+    let tri_verts = subdivide_corners(&corners, tessellation);
 
     let uv_corners = geometry::Rect {
         min: na::Point2::new(0.0, 0.0),
@@ -50,21 +90,16 @@ pub fn build_mesh(scene: &SceneState) -> (Vec<Vertex>, Vec<Instance>) {
     .corners();
 
     let mut uv_verts: Vec<TextureCoordinate> = vec![];
-    let uv_tri_verts: Vec<TextureCoordinate> = [
-        uv_corners[0],
-        uv_corners[1],
-        uv_corners[2],
-        uv_corners[0],
-        uv_corners[2],
-        uv_corners[3],
-    ]
-    .iter()
-    .map(|c| [c.x as f32, c.y as f32].into())
-    .collect();
+    let uv_tri_verts: Vec<TextureCoordinate> = subdivide_corners(&uv_corners, tessellation)
+        .iter()
+        .map(|c| [c.x as f32, c.y as f32].into())
+        .collect();
 
     let split = split_levels();
+    let mesh_levels = scene.mesh_levels.unwrap_or(split.mesh);
+    let instance_levels = scene.instance_levels.unwrap_or(split.instance);
 
-    state.process_levels(split.mesh, &mut |state| {
+    state.process_levels(mesh_levels, &mut |state| {
         for t in &tri_verts {
             let t2 = state.mat * t;
             positions.push([t2.x as f32, t2.y as f32].into());
@@ -82,18 +117,179 @@ pub fn build_mesh(scene: &SceneState) -> (Vec<Vertex>, Vec<Instance>) {
         .collect();
 
     let mut instances: Vec<Instance> = vec![];
-    state.process_levels(split.instance, &mut |state| {
-        let m: Matrix3<f64> = (root_mat * state.mat).to_homogeneous();
+    state.process_levels_styled(instance_levels, &mut |state, color, variation_weights| {
+        let m: Matrix3<f64> = state.mat.to_homogeneous();
         let s = m.as_slice();
         instances.push(Instance {
             row0: [s[0] as f32, s[3] as f32, s[6] as f32, 0f32],
             row1: [s[1] as f32, s[4] as f32, s[7] as f32, 0f32],
+            color: [color as f32, 0f32, 0f32, 0f32],
+            variation_weights: variation_weights.map(|w| w as f32),
         });
     });
 
     return (verts, instances);
 }
 
+/// Bilinearly subdivides the quad `corners` (in the same winding order
+/// `build_mesh`'s two-triangle fan already assumed: `0->1->2->3->0`) into a
+/// `divisions x divisions` grid of smaller quads, each emitted as two
+/// triangles. `divisions == 1` reproduces the original single-quad fan.
+fn subdivide_corners(corners: &[na::Point2<f64>], divisions: u32) -> Vec<na::Point2<f64>> {
+    let n = divisions.max(1);
+    let lerp = |a: na::Point2<f64>, b: na::Point2<f64>, t: f64| {
+        na::Point2::new(a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * t)
+    };
+    let point = |u: f64, v: f64| {
+        let top = lerp(corners[0], corners[1], u);
+        let bottom = lerp(corners[3], corners[2], u);
+        lerp(top, bottom, v)
+    };
+
+    let mut verts = vec![];
+    for j in 0..n {
+        for i in 0..n {
+            let u0 = i as f64 / n as f64;
+            let u1 = (i + 1) as f64 / n as f64;
+            let v0 = j as f64 / n as f64;
+            let v1 = (j + 1) as f64 / n as f64;
+            let p00 = point(u0, v0);
+            let p10 = point(u1, v0);
+            let p11 = point(u1, v1);
+            let p01 = point(u0, v1);
+            verts.extend([p00, p10, p11, p00, p11, p01]);
+        }
+    }
+    verts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit_square() -> [na::Point2<f64>; 4] {
+        [
+            na::Point2::new(0.0, 0.0),
+            na::Point2::new(1.0, 0.0),
+            na::Point2::new(1.0, 1.0),
+            na::Point2::new(0.0, 1.0),
+        ]
+    }
+
+    #[test]
+    fn one_division_reproduces_the_original_single_quad_fan() {
+        let corners = unit_square();
+        let verts = subdivide_corners(&corners, 1);
+        assert_eq!(
+            verts,
+            vec![corners[0], corners[1], corners[2], corners[0], corners[2], corners[3]]
+        );
+    }
+
+    #[test]
+    fn two_divisions_emit_four_quads_covering_the_same_bounds() {
+        let corners = unit_square();
+        let verts = subdivide_corners(&corners, 2);
+
+        assert_eq!(verts.len(), 4 * 6);
+        for v in &verts {
+            assert!(v.x >= 0.0 && v.x <= 1.0);
+            assert!(v.y >= 0.0 && v.y <= 1.0);
+        }
+
+        let midpoint = na::Point2::new(0.5, 0.5);
+        assert!(verts.iter().any(|v| (v.x - midpoint.x).abs() < 1e-12
+            && (v.y - midpoint.y).abs() < 1e-12));
+    }
+
+    #[test]
+    fn zero_divisions_is_clamped_to_one() {
+        let corners = unit_square();
+        assert_eq!(subdivide_corners(&corners, 0), subdivide_corners(&corners, 1));
+    }
+}
+
+/// The `N` base affine generators of the current IFS, blended once against
+/// the root's seed color/variation weights (`process_levels_styled(1, ...)`)
+/// — the depth-1 leaves, with no letterboxing folded in: `compute_expand`
+/// composes these against each other directly, level after level, so baking
+/// `root_mat` into them here would re-multiply it once per composed level
+/// instead of once overall. `root_transform` below is the single place
+/// `root_mat` gets applied, as a uniform in the accumulate vertex shader
+/// after the GPU (or CPU) expansion is complete.
+///
+/// This is the depth-1 *seed* `compute_expand::ComputeExpansion` starts its
+/// ping-pong from, not the per-level compose operand — that's
+/// `build_raw_generators`, the generators' own unblended values, used at
+/// every level after the seed.
+pub fn build_generators(scene: &SceneState) -> Vec<Instance> {
+    let root = get_state([scene.cursor.x + 1.0, scene.cursor.y + 1.0], [2.0, 2.0]);
+    let state = with_scene_generators(root.get_state(), scene);
+
+    let mut generators: Vec<Instance> = vec![];
+    state.process_levels_styled(1, &mut |state, color, variation_weights| {
+        let m: Matrix3<f64> = state.mat.to_homogeneous();
+        let s = m.as_slice();
+        generators.push(Instance {
+            row0: [s[0] as f32, s[3] as f32, s[6] as f32, 0f32],
+            row1: [s[1] as f32, s[4] as f32, s[7] as f32, 0f32],
+            color: [color as f32, 0f32, 0f32, 0f32],
+            variation_weights: variation_weights.map(|w| w as f32),
+        });
+    });
+    generators
+}
+
+/// The `N` base generators' own raw `mat`/`color`/`variation_weights`, with
+/// none of `process_levels_styled`'s running blend applied. This is the
+/// operand `compute_expand::ComputeExpansion` composes the previous level's
+/// instances against at every dispatch — reusing `build_generators`'s
+/// already depth-1-blended output there instead would silently re-blend an
+/// already-blended value at every level past the first, diverging from the
+/// CPU walk in `flame::State::process_levels_styled`.
+pub fn build_raw_generators(scene: &SceneState) -> Vec<Instance> {
+    let root = get_state([scene.cursor.x + 1.0, scene.cursor.y + 1.0], [2.0, 2.0]);
+    let state = with_scene_generators(root.get_state(), scene);
+
+    state
+        .generators()
+        .iter()
+        .map(|generator| {
+            let m: Matrix3<f64> = generator.mat.to_homogeneous();
+            let s = m.as_slice();
+            Instance {
+                row0: [s[0] as f32, s[3] as f32, s[6] as f32, 0f32],
+                row1: [s[1] as f32, s[4] as f32, s[7] as f32, 0f32],
+                color: [generator.color as f32, 0f32, 0f32, 0f32],
+                variation_weights: generator.variation_weights.map(|w| w as f32),
+            }
+        })
+        .collect()
+}
+
+/// The letterboxing transform that fits the current IFS's bounds into the
+/// `[-1, 1]` clip-space square, applied exactly once — as a uniform in the
+/// accumulate vertex shader, after every instance's (possibly many levels
+/// deep) generator composition — rather than baked into each generator or
+/// instance, which would multiply it in once per recursion level.
+pub fn root_transform(scene: &SceneState) -> ([f32; 4], [f32; 4]) {
+    let root = get_state([scene.cursor.x + 1.0, scene.cursor.y + 1.0], [2.0, 2.0]);
+    let bounds = root.get_state().get_bounds();
+    let root_mat = geometry::letter_box(
+        geometry::Rect {
+            min: na::Point2::new(-1.0, -1.0),
+            max: na::Point2::new(1.0, 1.0),
+        },
+        bounds,
+    );
+    let m: Matrix3<f64> = root_mat.to_homogeneous();
+    let s = m.as_slice();
+    (
+        [s[0] as f32, s[3] as f32, s[6] as f32, 0f32],
+        [s[1] as f32, s[4] as f32, s[7] as f32, 0f32],
+    )
+}
+
 pub fn build_quad() -> Vec<Vertex> {
     let corners = geometry::Rect {
         min: na::Point2::new(-1.0, -1.0),
@@ -0,0 +1,324 @@
+use wgpu::util::DeviceExt;
+
+use crate::mesh::Instance;
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct Counters {
+    parent_count: u32,
+    count: u32,
+    capacity: u32,
+    _padding: u32,
+}
+
+/// GPU-side replacement for walking `process_levels_styled` on the CPU: the
+/// `N` base generators are uploaded once, then one compute dispatch per
+/// recursion level composes the previous level's instances with every
+/// generator into the next level, ping-ponging between two storage buffers.
+/// The final level's atomic instance counter feeds a `draw_indirect` call
+/// directly, so the render loop never reads the instance count back to the
+/// CPU. Only used on backends that report compute shader support; the plain
+/// CPU path in `build_mesh` remains the fallback everywhere else.
+pub struct ComputeExpansion {
+    pipeline: wgpu::ComputePipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+
+    /// The depth-1 seed (`mesh::build_generators`'s output, already blended
+    /// once against the root), copied into `levels[0]` at the start of every
+    /// `expand` — the base case the first compose dispatch composes against.
+    seed_buffer: wgpu::Buffer,
+    /// The generators' own raw, unblended values (`mesh::build_raw_generators`'s
+    /// output), composed against the growing level buffer at every dispatch.
+    /// Reusing `seed_buffer` here instead would re-blend an already-blended
+    /// value every level past the first, diverging from the CPU walk in
+    /// `flame::State::process_levels_styled`.
+    generators_buffer: wgpu::Buffer,
+    generator_count: u32,
+
+    levels: [wgpu::Buffer; 2],
+    counters_buffer: wgpu::Buffer,
+    capacity: u32,
+
+    /// Clamps `counters.count` back down to `counters.capacity` after the
+    /// final `expand` dispatch, before it's copied into `indirect_buffer` —
+    /// the atomic tally keeps incrementing past capacity even though writes
+    /// past it are already guarded in `expand.wgsl`, so left unclamped a
+    /// `draw_indirect` can ask the GPU to read past the end of `levels[]`.
+    clamp_count_pipeline: wgpu::ComputePipeline,
+    clamp_count_bind_group: wgpu::BindGroup,
+
+    pub indirect_buffer: wgpu::Buffer,
+}
+
+const WORKGROUP_SIZE: u32 = 64;
+
+impl ComputeExpansion {
+    pub fn new(
+        device: &wgpu::Device,
+        seed: &[Instance],
+        generators: &[Instance],
+        max_levels: u32,
+    ) -> Self {
+        let generator_count = generators.len() as u32;
+        let capacity = generator_count.saturating_pow(max_levels).max(generator_count);
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("flame-expand"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shaders/expand.wgsl").into()),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("flame-expand-bind-group-layout"),
+            entries: &(0..4)
+                .map(|binding| wgpu::BindGroupLayoutEntry {
+                    binding,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage {
+                            read_only: binding < 2,
+                        },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                })
+                .collect::<Vec<_>>(),
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("flame-expand-pipeline-layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("flame-expand-pipeline"),
+            layout: Some(&layout),
+            module: &shader,
+            entry_point: "expand",
+        });
+
+        let seed_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("flame-seed-buffer"),
+            contents: bytemuck::cast_slice(seed),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let generators_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("flame-generators-buffer"),
+            contents: bytemuck::cast_slice(generators),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let level_size = (capacity as u64) * std::mem::size_of::<Instance>() as u64;
+        let levels = [
+            create_level_buffer(device, "flame-instances-a", level_size),
+            create_level_buffer(device, "flame-instances-b", level_size),
+        ];
+
+        let counters_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("flame-expand-counters"),
+            size: std::mem::size_of::<Counters>() as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let indirect_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("flame-expand-indirect"),
+            size: std::mem::size_of::<[u32; 4]>() as u64,
+            usage: wgpu::BufferUsages::INDIRECT | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let clamp_count_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("flame-expand-clamp-count-bind-group-layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+        let clamp_count_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("flame-expand-clamp-count-pipeline-layout"),
+            bind_group_layouts: &[&clamp_count_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let clamp_count_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("flame-expand-clamp-count-pipeline"),
+            layout: Some(&clamp_count_layout),
+            module: &shader,
+            entry_point: "clamp_count",
+        });
+        let clamp_count_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("flame-expand-clamp-count-bind-group"),
+            layout: &clamp_count_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 3,
+                resource: counters_buffer.as_entire_binding(),
+            }],
+        });
+
+        ComputeExpansion {
+            pipeline,
+            bind_group_layout,
+            seed_buffer,
+            generators_buffer,
+            generator_count,
+            levels,
+            counters_buffer,
+            capacity,
+            clamp_count_pipeline,
+            clamp_count_bind_group,
+            indirect_buffer,
+        }
+    }
+
+    /// Upper bound on the instance count after a full expansion, used to
+    /// size the ping-pong buffers.
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    /// Re-uploads the seed and base generators (e.g. after the user edits
+    /// them) without rebuilding the rest of the pipeline.
+    pub fn set_generators(&mut self, queue: &wgpu::Queue, seed: &[Instance], generators: &[Instance]) {
+        debug_assert_eq!(seed.len() as u32, self.generator_count);
+        debug_assert_eq!(generators.len() as u32, self.generator_count);
+        queue.write_buffer(&self.seed_buffer, 0, bytemuck::cast_slice(seed));
+        queue.write_buffer(&self.generators_buffer, 0, bytemuck::cast_slice(generators));
+    }
+
+    /// Expands the depth-1 seed with `levels - 1` compute passes, so the
+    /// returned instance buffer holds instances `levels.max(1)` generator
+    /// applications deep, like `flame::State::process_levels_styled(levels, ..)`
+    /// on the CPU, and writes the resulting
+    /// instance count into `indirect_buffer` so it can be used directly with
+    /// `RenderPass::draw_indirect`.
+    pub fn expand(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        vertex_count: u32,
+        levels: u32,
+    ) -> &wgpu::Buffer {
+        // `wgpu::RenderPass::draw_indirect` reads a 4x u32
+        // `[vertex_count, instance_count, first_vertex, first_instance]` record;
+        // `instance_count` gets overwritten by the `copy_buffer_to_buffer` below
+        // once the compute passes have tallied it.
+        let indirect_args: [u32; 4] = [vertex_count, 0, 0, 0];
+        queue.write_buffer(&self.indirect_buffer, 0, bytemuck::cast_slice(&indirect_args));
+
+        let mut parent_count = self.generator_count;
+        let mut current = 0usize;
+
+        // Level 0 is the depth-1 seed, not the raw generators below.
+        encoder.copy_buffer_to_buffer(
+            &self.seed_buffer,
+            0,
+            &self.levels[current],
+            0,
+            (self.generator_count as u64) * std::mem::size_of::<Instance>() as u64,
+        );
+        // When no dispatch follows, the seed itself is the final level.
+        queue.write_buffer(
+            &self.counters_buffer,
+            0,
+            bytemuck::bytes_of(&Counters {
+                parent_count,
+                count: parent_count,
+                capacity: self.capacity,
+                _padding: 0,
+            }),
+        );
+
+        for _ in 0..levels.saturating_sub(1) {
+            let next = 1 - current;
+            queue.write_buffer(
+                &self.counters_buffer,
+                0,
+                bytemuck::bytes_of(&Counters {
+                    parent_count,
+                    count: 0,
+                    capacity: self.capacity,
+                    _padding: 0,
+                }),
+            );
+
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("flame-expand-bind-group"),
+                layout: &self.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: self.generators_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: self.levels[current].as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: self.levels[next].as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: self.counters_buffer.as_entire_binding(),
+                    },
+                ],
+            });
+
+            {
+                let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                    label: Some("flame-expand-pass"),
+                    timestamp_writes: None,
+                });
+                pass.set_pipeline(&self.pipeline);
+                pass.set_bind_group(0, &bind_group, &[]);
+                let total = parent_count * self.generator_count;
+                let workgroups = total.div_ceil(WORKGROUP_SIZE).max(1);
+                pass.dispatch_workgroups(workgroups, 1, 1);
+            }
+
+            parent_count = (parent_count * self.generator_count).min(self.capacity);
+            current = next;
+        }
+
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("flame-expand-clamp-count-pass"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.clamp_count_pipeline);
+            pass.set_bind_group(0, &self.clamp_count_bind_group, &[]);
+            pass.dispatch_workgroups(1, 1, 1);
+        }
+
+        // `Counters::count` (offset 4) lands on `instance_count` (offset 4) of
+        // `DrawIndirectArgs`, so the (now capacity-clamped) atomic tally
+        // becomes the draw argument without ever round-tripping through the
+        // CPU.
+        encoder.copy_buffer_to_buffer(&self.counters_buffer, 4, &self.indirect_buffer, 4, 4);
+
+        &self.levels[current]
+    }
+}
+
+fn create_level_buffer(device: &wgpu::Device, label: &str, size: u64) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some(label),
+        size: size.max(std::mem::size_of::<Instance>() as u64),
+        // `VERTEX` lets the render pass bind a level buffer directly as the
+        // instance buffer with no copy between the compute and draw passes.
+        usage: wgpu::BufferUsages::STORAGE
+            | wgpu::BufferUsages::VERTEX
+            | wgpu::BufferUsages::COPY_DST
+            | wgpu::BufferUsages::COPY_SRC,
+        mapped_at_creation: false,
+    })
+}
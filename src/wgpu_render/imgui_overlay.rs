@@ -0,0 +1,475 @@
+use wgpu::util::DeviceExt;
+
+/// One editable IFS generator: an affine map (row-major 2x3, bottom row
+/// implicit `[0, 0, 1]`) plus the color index and relative pick weight the
+/// flame algorithm associates with it, and the weighted blend of nonlinear
+/// variation functions (`linear`, `sinusoidal`, `spherical`, `swirl`, in that
+/// order — see `shaders/variations.wgsl`) applied after the affine map.
+#[derive(Clone, Debug)]
+pub struct GeneratorParams {
+    pub mat: [[f32; 3]; 2],
+    pub color: f32,
+    pub weight: f32,
+    pub variation_weights: [f32; 4],
+}
+
+impl Default for GeneratorParams {
+    fn default() -> Self {
+        GeneratorParams {
+            mat: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+            color: 0.5,
+            weight: 1.0,
+            variation_weights: [1.0, 0.0, 0.0, 0.0],
+        }
+    }
+}
+
+impl GeneratorParams {
+    /// Converts this editor-facing row-major 2x3 matrix into the
+    /// `flame::Generator` the IFS walk actually consumes.
+    pub fn to_generator(&self) -> crate::flame::Generator {
+        let [r0, r1] = self.mat;
+        let m = nalgebra::Matrix3::new(
+            r0[0] as f64, r0[1] as f64, r0[2] as f64,
+            r1[0] as f64, r1[1] as f64, r1[2] as f64,
+            0.0, 0.0, 1.0,
+        );
+        crate::flame::Generator {
+            mat: nalgebra::Affine2::from_matrix_unchecked(m),
+            color: self.color as f64,
+            variation_weights: self.variation_weights.map(|w| w as f64),
+        }
+    }
+}
+
+/// Everything the overlay can edit live. The host is responsible for feeding
+/// this into `get_state`/`SceneState` on the next `Pipeline::prepare` — the
+/// overlay only owns the UI and the editable values, not the IFS engine
+/// itself.
+///
+/// Deliberately not `Default`: unlike `SceneState`'s own `Option<usize>`
+/// level overrides, these are plain `usize`/`f32`, so a derived `0`/`0.0`
+/// would collapse the walk to one degenerate triangle and divide tonemap's
+/// gamma curve by zero the instant an editor is attached. Build one with
+/// `EditorState::new` instead, which seeds from the scene it'll edit.
+#[derive(Clone, Debug)]
+pub struct EditorState {
+    pub generators: Vec<GeneratorParams>,
+    pub mesh_levels: usize,
+    pub instance_levels: usize,
+    pub gamma: f32,
+    pub palette: Vec<[f32; 4]>,
+    /// Mesh grid density fed into `SceneState::tessellation`; curved
+    /// variations need finer geometry than the flat affine tiling did.
+    pub tessellation: u32,
+}
+
+impl EditorState {
+    /// Seeds the editor from `scene`'s current (or already-overridden)
+    /// levels/gamma/tessellation, so attaching the overlay renders identically
+    /// to the frame before it was attached, until the user actually drags
+    /// something.
+    pub fn new(scene: &crate::wgpu_render::SceneState) -> Self {
+        let split = crate::split_levels();
+        EditorState {
+            generators: vec![],
+            mesh_levels: scene.mesh_levels.unwrap_or(split.mesh),
+            instance_levels: scene.instance_levels.unwrap_or(split.instance),
+            gamma: scene.gamma,
+            palette: vec![],
+            tessellation: scene.tessellation,
+        }
+    }
+
+    /// Feeds this frame's edited values into `scene`, so that dragging a
+    /// slider actually changes what the next `Pipeline::prepare` draws
+    /// instead of only the UI's own copy. Called from `Pipeline::prepare`
+    /// itself when an editor is attached.
+    pub fn apply_to(&self, scene: &mut crate::wgpu_render::SceneState) {
+        scene.generators = self.generators.iter().map(GeneratorParams::to_generator).collect();
+        scene.mesh_levels = Some(self.mesh_levels);
+        scene.instance_levels = Some(self.instance_levels);
+        scene.gamma = self.gamma;
+        if !self.palette.is_empty() {
+            scene.palette = self.palette.clone();
+        }
+        scene.tessellation = self.tessellation;
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct Vertex {
+    position: [f32; 2],
+    uv: [f32; 2],
+    color: [u8; 4],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct OverlayUniform {
+    scale: [f32; 2],
+    translate: [f32; 2],
+}
+
+/// Imgui render group composited over the fractal, structured like
+/// `rendy_render::mesh_pipeline`'s `SimpleGraphicsPipelineDesc`/
+/// `SimpleGraphicsPipeline` split: a `*Desc` builds device resources once,
+/// the pipeline itself rebuilds its per-frame vertex/index buffers from the
+/// imgui draw lists on every `prepare`.
+pub struct ImguiOverlayDesc;
+
+pub struct ImguiOverlay {
+    context: imgui::Context,
+    pipeline: wgpu::RenderPipeline,
+    _bind_group_layout: wgpu::BindGroupLayout,
+    font_bind_group: wgpu::BindGroup,
+    _font_texture: wgpu::Texture,
+    _sampler: wgpu::Sampler,
+    uniform_buffer: wgpu::Buffer,
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    draw_lists: Vec<DrawList>,
+    /// Window size passed to the last `prepare` call, used to clamp clip
+    /// rects to the render target's actual extent in `render`.
+    window_size: [f32; 2],
+}
+
+struct DrawList {
+    vertex_offset: u32,
+    index_offset: u32,
+    commands: Vec<DrawCommand>,
+}
+
+struct DrawCommand {
+    elem_count: u32,
+    index_offset: u32,
+    vertex_offset: i32,
+    clip_rect: [f32; 4],
+}
+
+impl ImguiOverlayDesc {
+    pub fn build(
+        self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        target_format: wgpu::TextureFormat,
+    ) -> ImguiOverlay {
+        let mut context = imgui::Context::create();
+        context.set_ini_filename(None);
+
+        let font_atlas = context.fonts().build_rgba32_texture();
+        let font_texture = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some("flame-imgui-font-atlas"),
+                size: wgpu::Extent3d {
+                    width: font_atlas.width,
+                    height: font_atlas.height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            font_atlas.data,
+        );
+        let font_view = font_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        context.fonts().tex_id = imgui::TextureId::from(0);
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("flame-imgui-sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("flame-imgui-bind-group-layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("flame-imgui-uniform"),
+            size: std::mem::size_of::<OverlayUniform>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let font_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("flame-imgui-bind-group"),
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&font_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("flame-imgui"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shaders/imgui.wgsl").into()),
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("flame-imgui-pipeline-layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("flame-imgui-pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x2, 2 => Unorm8x4],
+                }],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: target_format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        let vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("flame-imgui-vertex-buffer"),
+            size: 0,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let index_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("flame-imgui-index-buffer"),
+            size: 0,
+            usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        ImguiOverlay {
+            context,
+            pipeline,
+            _bind_group_layout: bind_group_layout,
+            font_bind_group,
+            _font_texture: font_texture,
+            _sampler: sampler,
+            uniform_buffer,
+            vertex_buffer,
+            index_buffer,
+            draw_lists: vec![],
+            window_size: [0.0, 0.0],
+        }
+    }
+}
+
+impl ImguiOverlay {
+    /// Runs one imgui frame: builds the generator/level/palette editor UI
+    /// against `editor`, then rebuilds the vertex/index buffers from the
+    /// resulting draw lists. Mirrors `Pipeline::prepare`'s "rebuild buffers
+    /// for this frame" shape from `mesh_pipeline`.
+    pub fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        window_size: [f32; 2],
+        delta_time: f32,
+        editor: &mut EditorState,
+    ) {
+        self.window_size = window_size;
+        let io = self.context.io_mut();
+        io.display_size = window_size;
+        io.update_delta_time(std::time::Duration::from_secs_f32(delta_time.max(1.0 / 1000.0)));
+
+        let ui = self.context.new_frame();
+        ui.window("Flame editor").build(|| {
+            ui.slider("gamma", 0.5, 4.0, &mut editor.gamma);
+            ui.slider("mesh levels", 0, 8, &mut editor.mesh_levels);
+            ui.slider("instance levels", 0, 12, &mut editor.instance_levels);
+            ui.slider("tessellation", 1, 16, &mut editor.tessellation);
+
+            for (i, generator) in editor.generators.iter_mut().enumerate() {
+                ui.separator();
+                ui.text(format!("generator {i}"));
+                ui.input_float3(format!("row0##{i}"), &mut generator.mat[0]).build();
+                ui.input_float3(format!("row1##{i}"), &mut generator.mat[1]).build();
+                ui.slider(format!("color##{i}"), 0.0, 1.0, &mut generator.color);
+                ui.slider(format!("weight##{i}"), 0.0, 4.0, &mut generator.weight);
+                ui.slider(format!("linear##{i}"), 0.0, 1.0, &mut generator.variation_weights[0]);
+                ui.slider(
+                    format!("sinusoidal##{i}"),
+                    0.0,
+                    1.0,
+                    &mut generator.variation_weights[1],
+                );
+                ui.slider(format!("spherical##{i}"), 0.0, 1.0, &mut generator.variation_weights[2]);
+                ui.slider(format!("swirl##{i}"), 0.0, 1.0, &mut generator.variation_weights[3]);
+            }
+
+            if ui.button("add generator") {
+                editor.generators.push(GeneratorParams::default());
+            }
+        });
+
+        let draw_data = self.context.render();
+        self.upload_draw_data(device, queue, draw_data);
+    }
+
+    fn upload_draw_data(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        draw_data: &imgui::DrawData,
+    ) {
+        let mut vertices: Vec<Vertex> = vec![];
+        let mut indices: Vec<u16> = vec![];
+        let mut draw_lists = vec![];
+
+        for draw_list in draw_data.draw_lists() {
+            let vertex_offset = vertices.len() as u32;
+            let index_offset = indices.len() as u32;
+
+            vertices.extend(draw_list.vtx_buffer().iter().map(|v| Vertex {
+                position: v.pos,
+                uv: v.uv,
+                color: v.col,
+            }));
+            indices.extend_from_slice(draw_list.idx_buffer());
+
+            let commands = draw_list
+                .commands()
+                .filter_map(|command| match command {
+                    imgui::DrawCmd::Elements { count, cmd_params } => Some(DrawCommand {
+                        elem_count: count as u32,
+                        index_offset: cmd_params.idx_offset as u32,
+                        vertex_offset: cmd_params.vtx_offset as i32,
+                        clip_rect: cmd_params.clip_rect,
+                    }),
+                    _ => None,
+                })
+                .collect();
+
+            draw_lists.push(DrawList {
+                vertex_offset,
+                index_offset,
+                commands,
+            });
+        }
+
+        self.vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("flame-imgui-vertex-buffer"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        self.index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("flame-imgui-index-buffer"),
+            contents: bytemuck::cast_slice(&indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+        self.draw_lists = draw_lists;
+
+        queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::bytes_of(&OverlayUniform {
+                scale: [
+                    2.0 / draw_data.display_size[0].max(1.0),
+                    -2.0 / draw_data.display_size[1].max(1.0),
+                ],
+                translate: [-1.0, 1.0],
+            }),
+        );
+    }
+
+    /// Composites the last-prepared frame's draw lists over `target`, using
+    /// `Load` instead of `Clear` so the fractal underneath is preserved.
+    pub fn render(&self, encoder: &mut wgpu::CommandEncoder, target: &wgpu::TextureView) {
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("flame-imgui-pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &self.font_bind_group, &[]);
+        pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+
+        let [target_width, target_height] = self.window_size;
+        for draw_list in &self.draw_lists {
+            for command in &draw_list.commands {
+                let [x0, y0, x1, y1] = command.clip_rect;
+                let x0 = x0.max(0.0);
+                let y0 = y0.max(0.0);
+                let x1 = x1.min(target_width);
+                let y1 = y1.min(target_height);
+                if x1 <= x0 || y1 <= y0 {
+                    continue;
+                }
+                pass.set_scissor_rect(x0 as u32, y0 as u32, (x1 - x0) as u32, (y1 - y0) as u32);
+
+                let start = draw_list.index_offset + command.index_offset;
+                let base_vertex = draw_list.vertex_offset as i32 + command.vertex_offset;
+                pass.draw_indexed(start..(start + command.elem_count), base_vertex, 0..1);
+            }
+        }
+    }
+
+    pub fn io_mut(&mut self) -> &mut imgui::Io {
+        self.context.io_mut()
+    }
+}
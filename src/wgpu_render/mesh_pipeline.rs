@@ -0,0 +1,743 @@
+use std::path::PathBuf;
+
+use wgpu::util::DeviceExt;
+
+use crate::mesh::{build_generators, build_mesh, build_quad, build_raw_generators, Instance, Vertex};
+use crate::wgpu_render::compute_expand::ComputeExpansion;
+use crate::wgpu_render::palette::build_palette_texture;
+use crate::wgpu_render::shader_preprocessor::{preprocess, ShaderFeature};
+use crate::wgpu_render::shader_watcher::ShaderWatcher;
+use crate::wgpu_render::SceneState;
+
+/// Texture format backing the density accumulation pass. `Rgba32Float` gives
+/// full HDR headroom; callers tight on memory can drop to `Rgba16Float`.
+pub const DEFAULT_ACCUMULATION_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba32Float;
+
+fn shaders_dir() -> PathBuf {
+    PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/src/wgpu_render/shaders"))
+}
+
+fn active_features(scene: &SceneState) -> Vec<ShaderFeature> {
+    let mut features = vec![];
+    if scene.tonemap {
+        features.push(ShaderFeature::Tonemap);
+    }
+    if scene.color_palette {
+        features.push(ShaderFeature::ColorPalette);
+    }
+    features
+}
+
+fn load_shader(
+    device: &wgpu::Device,
+    label: &str,
+    file_name: &str,
+    features: &[ShaderFeature],
+) -> wgpu::ShaderModule {
+    let source = preprocess(&shaders_dir().join(file_name), features)
+        .unwrap_or_else(|err| panic!("failed to preprocess {file_name}: {err}"));
+    device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some(label),
+        source: wgpu::ShaderSource::Wgsl(source.into()),
+    })
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct TonemapUniform {
+    gamma: f32,
+    max_density: f32,
+    _padding: [f32; 2],
+}
+
+/// The letterboxing transform, applied once in the accumulate vertex shader
+/// to every instance's fully composed point (see `mesh::root_transform`).
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct RootTransformUniform {
+    row0: [f32; 4],
+    row1: [f32; 4],
+}
+
+/// Two-pass HDR pipeline: pass one splats instanced triangles additively into
+/// an offscreen density texture, pass two tone-maps that texture onto the
+/// swapchain with a log-density curve.
+pub struct Pipeline {
+    swapchain_format: wgpu::TextureFormat,
+    accumulation_format: wgpu::TextureFormat,
+    accumulation_texture: wgpu::Texture,
+    accumulation_view: wgpu::TextureView,
+    accumulation_pipeline: wgpu::RenderPipeline,
+    palette_bind_group_layout: wgpu::BindGroupLayout,
+    palette_bind_group: wgpu::BindGroup,
+    palette_texture: wgpu::Texture,
+    palette: Vec<[f32; 4]>,
+    palette_sampler: wgpu::Sampler,
+
+    root_transform_bind_group_layout: wgpu::BindGroupLayout,
+    root_transform_bind_group: wgpu::BindGroup,
+    root_transform_buffer: wgpu::Buffer,
+
+    tonemap_pipeline: wgpu::RenderPipeline,
+    tonemap_bind_group_layout: wgpu::BindGroupLayout,
+    tonemap_bind_group: wgpu::BindGroup,
+    tonemap_uniform_buffer: wgpu::Buffer,
+    sampler: wgpu::Sampler,
+
+    quad_buffer: wgpu::Buffer,
+    vertex_buffer: wgpu::Buffer,
+    vertex_count: u32,
+    instance_buffer: wgpu::Buffer,
+    instance_count: u32,
+
+    /// GPU expansion of the IFS levels, when the backend supports compute
+    /// shaders. `None` means every frame falls back to the CPU path in
+    /// `build_mesh`/`prepare` above.
+    compute_expansion: Option<ComputeExpansion>,
+    /// The `levels` `enable_gpu_expansion` was called with — what `levels[]`
+    /// and `counters.capacity` were actually sized for. An upper bound on
+    /// `expansion_levels`, not the expansion depth itself.
+    max_expansion_levels: u32,
+    /// How many levels deep `render` actually expands this frame; `prepare`
+    /// refreshes this from `scene.instance_levels` every call so the imgui
+    /// "instance levels" slider keeps working once GPU expansion is enabled.
+    expansion_levels: u32,
+
+    shader_features: Vec<ShaderFeature>,
+    shader_watcher: Option<ShaderWatcher>,
+
+    extent: wgpu::Extent3d,
+}
+
+impl Pipeline {
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        swapchain_format: wgpu::TextureFormat,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        Self::with_accumulation_format(
+            device,
+            queue,
+            swapchain_format,
+            width,
+            height,
+            DEFAULT_ACCUMULATION_FORMAT,
+        )
+    }
+
+    pub fn with_accumulation_format(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        swapchain_format: wgpu::TextureFormat,
+        width: u32,
+        height: u32,
+        accumulation_format: wgpu::TextureFormat,
+    ) -> Self {
+        let extent = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        let (accumulation_texture, accumulation_view) =
+            create_accumulation_texture(device, extent, accumulation_format);
+
+        let palette = crate::wgpu_render::palette::DEFAULT_PALETTE_STOPS.to_vec();
+        let (palette_texture, palette_view) = build_palette_texture(device, queue, &palette);
+        let palette_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("flame-palette-sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            ..Default::default()
+        });
+        let palette_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("flame-palette-bind-group-layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::VERTEX,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D1,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::VERTEX,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+            });
+        let palette_bind_group = create_palette_bind_group(
+            device,
+            &palette_bind_group_layout,
+            &palette_view,
+            &palette_sampler,
+        );
+
+        let root_transform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("flame-root-transform-uniform"),
+            contents: bytemuck::bytes_of(&RootTransformUniform {
+                row0: [1.0, 0.0, 0.0, 0.0],
+                row1: [0.0, 1.0, 0.0, 0.0],
+            }),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let root_transform_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("flame-root-transform-bind-group-layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+        let root_transform_bind_group = create_root_transform_bind_group(
+            device,
+            &root_transform_bind_group_layout,
+            &root_transform_buffer,
+        );
+
+        let features = active_features(&SceneState::default());
+
+        let accumulate_shader =
+            load_shader(device, "flame-accumulate", "accumulate.wgsl", &features);
+        let accumulation_pipeline = create_accumulation_pipeline(
+            device,
+            &accumulate_shader,
+            &palette_bind_group_layout,
+            &root_transform_bind_group_layout,
+            accumulation_format,
+        );
+
+        let tonemap_shader = load_shader(device, "flame-tonemap", "tonemap.wgsl", &features);
+        let tonemap_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("flame-tonemap-bind-group-layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ],
+            });
+        let tonemap_pipeline = create_tonemap_pipeline(
+            device,
+            &tonemap_shader,
+            &tonemap_bind_group_layout,
+            swapchain_format,
+        );
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("flame-accumulation-sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let tonemap_uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("flame-tonemap-uniform"),
+            contents: bytemuck::bytes_of(&TonemapUniform {
+                gamma: 2.2,
+                max_density: 1.0,
+                _padding: [0.0; 2],
+            }),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let tonemap_bind_group = create_tonemap_bind_group(
+            device,
+            &tonemap_bind_group_layout,
+            &accumulation_view,
+            &sampler,
+            &tonemap_uniform_buffer,
+        );
+
+        let quad = build_quad();
+        let quad_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("flame-quad-vertex-buffer"),
+            contents: bytemuck::cast_slice(&quad),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("flame-vertex-buffer"),
+            size: 0,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let instance_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("flame-instance-buffer"),
+            size: 0,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Pipeline {
+            swapchain_format,
+            accumulation_format,
+            accumulation_texture,
+            accumulation_view,
+            accumulation_pipeline,
+            palette_bind_group_layout,
+            palette_bind_group,
+            palette_texture,
+            palette,
+            palette_sampler,
+            root_transform_bind_group_layout,
+            root_transform_bind_group,
+            root_transform_buffer,
+            tonemap_pipeline,
+            tonemap_bind_group_layout,
+            tonemap_bind_group,
+            tonemap_uniform_buffer,
+            sampler,
+            quad_buffer,
+            vertex_buffer,
+            vertex_count: 0,
+            instance_buffer,
+            instance_count: 0,
+            compute_expansion: None,
+            max_expansion_levels: 0,
+            expansion_levels: 0,
+            shader_features: features,
+            shader_watcher: None,
+            extent,
+        }
+    }
+
+    /// Starts watching `shaders/` on disk; subsequent `prepare` calls will
+    /// recompile and hot-swap the accumulate/tonemap pipelines whenever a
+    /// shader file (or one of its `#import`s) changes.
+    pub fn watch_shaders(&mut self) {
+        match ShaderWatcher::new(&shaders_dir()) {
+            Ok(watcher) => self.shader_watcher = Some(watcher),
+            Err(err) => log::warn!("shader hot reload disabled: {err}"),
+        }
+    }
+
+    fn reload_shaders(&mut self, device: &wgpu::Device, scene: &SceneState) {
+        let features = active_features(scene);
+        let accumulate_shader =
+            load_shader(device, "flame-accumulate", "accumulate.wgsl", &features);
+        self.accumulation_pipeline = create_accumulation_pipeline(
+            device,
+            &accumulate_shader,
+            &self.palette_bind_group_layout,
+            &self.root_transform_bind_group_layout,
+            self.accumulation_format,
+        );
+
+        let tonemap_shader = load_shader(device, "flame-tonemap", "tonemap.wgsl", &features);
+        self.tonemap_pipeline = create_tonemap_pipeline(
+            device,
+            &tonemap_shader,
+            &self.tonemap_bind_group_layout,
+            self.swapchain_format,
+        );
+        self.shader_features = features;
+    }
+
+    /// Switches the instance path over to the GPU compute expansion. Callers
+    /// should only do this once they've checked the backend actually supports
+    /// compute shaders (e.g. via `AdapterInfo`/`DownlevelCapabilities`); the
+    /// CPU path in `prepare` remains the default and the fallback otherwise.
+    pub fn enable_gpu_expansion(&mut self, device: &wgpu::Device, scene: &SceneState, levels: u32) {
+        let seed = build_generators(scene);
+        let generators = build_raw_generators(scene);
+        self.compute_expansion = Some(ComputeExpansion::new(device, &seed, &generators, levels));
+        self.max_expansion_levels = levels;
+        self.expansion_levels = levels;
+    }
+
+    /// Recreates the accumulation texture after a swapchain resize.
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        self.extent = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        let (texture, view) =
+            create_accumulation_texture(device, self.extent, self.accumulation_format);
+        self.accumulation_texture = texture;
+        self.accumulation_view = view;
+        self.tonemap_bind_group = create_tonemap_bind_group(
+            device,
+            &self.tonemap_bind_group_layout,
+            &self.accumulation_view,
+            &self.sampler,
+            &self.tonemap_uniform_buffer,
+        );
+    }
+
+    /// Rebuilds the mesh and instance buffers from the current scene, mirroring
+    /// `rendy_render::mesh_pipeline::Pipeline::prepare`. When `editor` is
+    /// attached, its live-edited generators/levels/gamma/tessellation are
+    /// applied to `scene` first, so the overlay's sliders actually affect what
+    /// gets drawn this frame.
+    pub fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        scene: &mut SceneState,
+        editor: Option<&crate::wgpu_render::imgui_overlay::EditorState>,
+    ) {
+        if let Some(editor) = editor {
+            editor.apply_to(scene);
+        }
+
+        let features = active_features(scene);
+        let watcher_changed = self
+            .shader_watcher
+            .as_ref()
+            .is_some_and(ShaderWatcher::poll_changed);
+        if watcher_changed || features != self.shader_features {
+            self.reload_shaders(device, scene);
+        }
+
+        let (verts, instances) = build_mesh(scene);
+
+        let (root_row0, root_row1) = crate::mesh::root_transform(scene);
+        queue.write_buffer(
+            &self.root_transform_buffer,
+            0,
+            bytemuck::bytes_of(&RootTransformUniform {
+                row0: root_row0,
+                row1: root_row1,
+            }),
+        );
+
+        self.vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("flame-vertex-buffer"),
+            contents: bytemuck::cast_slice(&verts),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        self.vertex_count = verts.len() as u32;
+
+        match &mut self.compute_expansion {
+            Some(compute_expansion) => {
+                let seed = build_generators(scene);
+                let generators = build_raw_generators(scene);
+                compute_expansion.set_generators(queue, &seed, &generators);
+
+                // Mirrors `build_mesh`'s own fallback so the imgui "instance
+                // levels" slider (`EditorState::instance_levels` via
+                // `apply_to`) still does something once GPU expansion is
+                // enabled — `max_expansion_levels` only bounds what
+                // `levels[]`/`counters.capacity` were sized for at
+                // `enable_gpu_expansion` time, it was never meant to pin the
+                // dispatch count for every later frame.
+                let split = crate::split_levels();
+                let requested = scene.instance_levels.unwrap_or(split.instance) as u32;
+                self.expansion_levels = requested.min(self.max_expansion_levels);
+            }
+            None => {
+                self.instance_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("flame-instance-buffer"),
+                    contents: bytemuck::cast_slice(&instances),
+                    usage: wgpu::BufferUsages::VERTEX,
+                });
+                self.instance_count = instances.len() as u32;
+            }
+        }
+
+        if scene.palette != self.palette {
+            self.palette = scene.palette.clone();
+            let (texture, view) = build_palette_texture(device, queue, &self.palette);
+            self.palette_texture = texture;
+            self.palette_bind_group = create_palette_bind_group(
+                device,
+                &self.palette_bind_group_layout,
+                &view,
+                &self.palette_sampler,
+            );
+        }
+
+        let max_density = scene.max_density.unwrap_or(DEFAULT_MAX_DENSITY);
+        queue.write_buffer(
+            &self.tonemap_uniform_buffer,
+            0,
+            bytemuck::bytes_of(&TonemapUniform {
+                gamma: scene.gamma,
+                max_density,
+                _padding: [0.0; 2],
+            }),
+        );
+    }
+
+    pub fn render(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+    ) {
+        let expanded = self
+            .compute_expansion
+            .as_ref()
+            .map(|compute_expansion| {
+                compute_expansion.expand(
+                    device,
+                    queue,
+                    encoder,
+                    self.vertex_count,
+                    self.expansion_levels,
+                )
+            });
+
+        {
+            let mut accumulate_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("flame-accumulate-pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &self.accumulation_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+            accumulate_pass.set_pipeline(&self.accumulation_pipeline);
+            accumulate_pass.set_bind_group(0, &self.palette_bind_group, &[]);
+            accumulate_pass.set_bind_group(1, &self.root_transform_bind_group, &[]);
+            accumulate_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+
+            match (&expanded, &self.compute_expansion) {
+                (Some(instance_buffer), Some(compute_expansion)) => {
+                    accumulate_pass.set_vertex_buffer(1, instance_buffer.slice(..));
+                    accumulate_pass.draw_indirect(&compute_expansion.indirect_buffer, 0);
+                }
+                _ => {
+                    accumulate_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+                    accumulate_pass.draw(0..self.vertex_count, 0..self.instance_count);
+                }
+            }
+        }
+
+        {
+            let mut tonemap_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("flame-tonemap-pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: target,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+            tonemap_pass.set_pipeline(&self.tonemap_pipeline);
+            tonemap_pass.set_bind_group(0, &self.tonemap_bind_group, &[]);
+            tonemap_pass.set_vertex_buffer(0, self.quad_buffer.slice(..));
+            tonemap_pass.draw(0..6, 0..1);
+        }
+    }
+}
+
+fn create_accumulation_texture(
+    device: &wgpu::Device,
+    extent: wgpu::Extent3d,
+    format: wgpu::TextureFormat,
+) -> (wgpu::Texture, wgpu::TextureView) {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("flame-accumulation-texture"),
+        size: extent,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    (texture, view)
+}
+
+fn create_accumulation_pipeline(
+    device: &wgpu::Device,
+    shader: &wgpu::ShaderModule,
+    palette_bind_group_layout: &wgpu::BindGroupLayout,
+    root_transform_bind_group_layout: &wgpu::BindGroupLayout,
+    format: wgpu::TextureFormat,
+) -> wgpu::RenderPipeline {
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("flame-accumulate-pipeline-layout"),
+        bind_group_layouts: &[palette_bind_group_layout, root_transform_bind_group_layout],
+        push_constant_ranges: &[],
+    });
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("flame-accumulate-pipeline"),
+        layout: Some(&layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: "vs_main",
+            buffers: &[Vertex::layout(), Instance::layout()],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: "fs_main",
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState {
+                    color: wgpu::BlendComponent {
+                        src_factor: wgpu::BlendFactor::One,
+                        dst_factor: wgpu::BlendFactor::One,
+                        operation: wgpu::BlendOperation::Add,
+                    },
+                    alpha: wgpu::BlendComponent {
+                        src_factor: wgpu::BlendFactor::One,
+                        dst_factor: wgpu::BlendFactor::One,
+                        operation: wgpu::BlendOperation::Add,
+                    },
+                }),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+    })
+}
+
+fn create_tonemap_pipeline(
+    device: &wgpu::Device,
+    shader: &wgpu::ShaderModule,
+    bind_group_layout: &wgpu::BindGroupLayout,
+    format: wgpu::TextureFormat,
+) -> wgpu::RenderPipeline {
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("flame-tonemap-pipeline-layout"),
+        bind_group_layouts: &[bind_group_layout],
+        push_constant_ranges: &[],
+    });
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("flame-tonemap-pipeline"),
+        layout: Some(&layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: "vs_main",
+            buffers: &[Vertex::layout()],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: "fs_main",
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: None,
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+    })
+}
+
+fn create_tonemap_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    accumulation_view: &wgpu::TextureView,
+    sampler: &wgpu::Sampler,
+    uniform_buffer: &wgpu::Buffer,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("flame-tonemap-bind-group"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(accumulation_view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: uniform_buffer.as_entire_binding(),
+            },
+        ],
+    })
+}
+
+fn create_root_transform_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    uniform_buffer: &wgpu::Buffer,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("flame-root-transform-bind-group"),
+        layout,
+        entries: &[wgpu::BindGroupEntry {
+            binding: 0,
+            resource: uniform_buffer.as_entire_binding(),
+        }],
+    })
+}
+
+fn create_palette_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    palette_view: &wgpu::TextureView,
+    sampler: &wgpu::Sampler,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("flame-palette-bind-group"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(palette_view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+        ],
+    })
+}
+
+/// Fallback exposure used when `SceneState::max_density` isn't pinned by the
+/// caller. A real per-pixel measurement would need a readback or downsample
+/// of the accumulation texture, which this pipeline doesn't do; this is a
+/// fixed, tunable exposure rather than a measurement of anything.
+pub const DEFAULT_MAX_DENSITY: f32 = 8.0;
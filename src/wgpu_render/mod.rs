@@ -0,0 +1,67 @@
+pub mod compute_expand;
+pub mod imgui_overlay;
+mod mesh_pipeline;
+mod palette;
+mod shader_preprocessor;
+mod shader_watcher;
+
+pub use mesh_pipeline::{Pipeline, DEFAULT_MAX_DENSITY};
+pub use palette::DEFAULT_PALETTE_STOPS;
+pub use shader_watcher::ShaderWatcher;
+
+use na::Point2;
+
+/// Per-frame state threaded from the windowing loop into the wgpu pipeline.
+#[derive(Debug, Clone)]
+pub struct SceneState {
+    pub cursor: Point2<f64>,
+    /// Exponent applied to the tone-mapped density, `output = mapped.powf(1.0 / gamma)`.
+    pub gamma: f32,
+    /// Density that maps to full brightness. `None` falls back to
+    /// `DEFAULT_MAX_DENSITY`, a fixed exposure rather than a measurement of
+    /// the actual accumulation texture — this pipeline has no
+    /// readback/downsample path, so pin this explicitly once a scene's real
+    /// peak density is known.
+    pub max_density: Option<f32>,
+    /// Ordered `rgba` stops (each channel `0.0..=1.0`) making up the 1-D color
+    /// palette every instance's blended color index is looked up against.
+    pub palette: Vec<[f32; 4]>,
+    /// Feature flags threaded into the WGSL preprocessor as `#ifdef TONEMAP`
+    /// / `#ifdef COLOR_PALETTE`. Turning them off is mainly useful for
+    /// debugging one pass in isolation.
+    pub tonemap: bool,
+    pub color_palette: bool,
+    /// Grid density each mesh triangle is subdivided into before `build_mesh`
+    /// transforms its corners, e.g. `4` turns one quad into a 4x4 grid of
+    /// quads (32 triangles). Nonlinear variations warp a triangle's interior
+    /// along with its corners, so flat single-quad geometry facets visibly
+    /// once a transform's variation weights move away from pure `linear`;
+    /// raising this trades vertex count for smoother curves.
+    pub tessellation: u32,
+    /// Live-edited generators from `imgui_overlay::EditorState`, spliced in
+    /// via `flame::State::with_generators`. Empty means "use whatever
+    /// `get_state`'s cursor lookup already returns" — the overlay only
+    /// populates this once the user has actually edited a generator.
+    pub generators: Vec<crate::flame::Generator>,
+    /// Overrides for `split_levels()`'s `mesh`/`instance` depths, likewise fed
+    /// by the overlay. `None` falls back to `split_levels()`.
+    pub mesh_levels: Option<usize>,
+    pub instance_levels: Option<usize>,
+}
+
+impl Default for SceneState {
+    fn default() -> Self {
+        SceneState {
+            cursor: Point2::new(0.0, 0.0),
+            gamma: 2.2,
+            max_density: None,
+            palette: DEFAULT_PALETTE_STOPS.to_vec(),
+            tonemap: true,
+            color_palette: true,
+            tessellation: 1,
+            generators: vec![],
+            mesh_levels: None,
+            instance_levels: None,
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use wgpu::util::DeviceExt;
+
+/// Width, in texels, of the baked palette strip. Flames only ever index the
+/// palette with a single scalar, so a modest strip is plenty smooth.
+const PALETTE_WIDTH: u32 = 256;
+
+/// A classic black -> red -> orange -> white fire palette, used when the
+/// caller doesn't supply their own stops.
+pub const DEFAULT_PALETTE_STOPS: [[f32; 4]; 4] = [
+    [0.0, 0.0, 0.0, 1.0],
+    [0.5, 0.0, 0.0, 1.0],
+    [1.0, 0.5, 0.0, 1.0],
+    [1.0, 1.0, 1.0, 1.0],
+];
+
+/// Linearly interpolates `stops` into a `PALETTE_WIDTH`-texel `Rgba8Unorm`
+/// strip and uploads it as a 1-D texture for the accumulate shader to sample.
+pub fn build_palette_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    stops: &[[f32; 4]],
+) -> (wgpu::Texture, wgpu::TextureView) {
+    let pixels = bake_palette(stops, PALETTE_WIDTH);
+
+    let texture = device.create_texture_with_data(
+        queue,
+        &wgpu::TextureDescriptor {
+            label: Some("flame-palette-texture"),
+            size: wgpu::Extent3d {
+                width: PALETTE_WIDTH,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D1,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        },
+        wgpu::util::TextureDataOrder::LayerMajor,
+        &pixels,
+    );
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    (texture, view)
+}
+
+fn bake_palette(stops: &[[f32; 4]], width: u32) -> Vec<u8> {
+    assert!(!stops.is_empty(), "palette needs at least one stop");
+    if stops.len() == 1 {
+        return (0..width)
+            .flat_map(|_| stops[0].map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8))
+            .collect();
+    }
+
+    let segments = (stops.len() - 1) as f32;
+    (0..width)
+        .flat_map(|i| {
+            let t = i as f32 / (width - 1).max(1) as f32 * segments;
+            let segment = (t.floor() as usize).min(stops.len() - 2);
+            let local_t = t - segment as f32;
+            let a = stops[segment];
+            let b = stops[segment + 1];
+            (0..4).map(move |c| {
+                let value = a[c] + (b[c] - a[c]) * local_t;
+                (value.clamp(0.0, 1.0) * 255.0).round() as u8
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_stop_fills_every_texel_with_the_same_color() {
+        let pixels = bake_palette(&[[0.2, 0.4, 0.6, 1.0]], 4);
+        let expected = [51, 102, 153, 255];
+        for chunk in pixels.chunks(4) {
+            assert_eq!(chunk, expected);
+        }
+    }
+
+    #[test]
+    fn endpoints_match_the_first_and_last_stop_exactly() {
+        let pixels = bake_palette(&DEFAULT_PALETTE_STOPS, PALETTE_WIDTH);
+        assert_eq!(&pixels[0..4], [0, 0, 0, 255]);
+        let last = (PALETTE_WIDTH as usize - 1) * 4;
+        assert_eq!(&pixels[last..last + 4], [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn midpoint_between_two_stops_is_the_linear_average() {
+        let stops = [[0.0, 0.0, 0.0, 0.0], [1.0, 1.0, 1.0, 1.0]];
+        let pixels = bake_palette(&stops, 3);
+        // width 3 -> t in {0, 1, 2} / 2 * 1 segment -> samples at 0.0, 0.5, 1.0
+        assert_eq!(&pixels[0..4], [0, 0, 0, 0]);
+        assert_eq!(&pixels[4..8], [128, 128, 128, 128]);
+        assert_eq!(&pixels[8..12], [255, 255, 255, 255]);
+    }
+}
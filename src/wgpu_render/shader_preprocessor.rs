@@ -0,0 +1,140 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Feature flags the preprocessor can gate `#ifdef` blocks on, derived from
+/// `SceneState` by the caller (e.g. `TONEMAP` follows whether tone-mapping is
+/// enabled, `COLOR_PALETTE` whether a palette is attached).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ShaderFeature {
+    Tonemap,
+    ColorPalette,
+}
+
+impl ShaderFeature {
+    fn ident(self) -> &'static str {
+        match self {
+            ShaderFeature::Tonemap => "TONEMAP",
+            ShaderFeature::ColorPalette => "COLOR_PALETTE",
+        }
+    }
+}
+
+/// A small naga_oil-style preprocessor: splices `#import "module.wgsl"`
+/// directives (paths resolved relative to the importing file, deduplicated by
+/// canonical path so a module pulled in through two different chains is only
+/// emitted once) and strips `#ifdef`/`#else`/`#endif` blocks gated on
+/// `features`, before the result is handed to
+/// `wgpu::Device::create_shader_module`.
+pub fn preprocess(entry: &Path, features: &[ShaderFeature]) -> std::io::Result<String> {
+    let mut seen = HashSet::new();
+    let mut out = String::new();
+    splice(entry, features, &mut seen, &mut out)?;
+    Ok(out)
+}
+
+fn splice(
+    path: &Path,
+    features: &[ShaderFeature],
+    seen: &mut HashSet<PathBuf>,
+    out: &mut String,
+) -> std::io::Result<()> {
+    let canonical = path.canonicalize()?;
+    if !seen.insert(canonical) {
+        return Ok(());
+    }
+
+    let source = std::fs::read_to_string(path)?;
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+
+    // Each frame is `(parent_active, local_active)`; the line at the top of
+    // the stack is emitted only when both are true.
+    let mut stack: Vec<(bool, bool)> = vec![];
+    let active = |stack: &[(bool, bool)]| stack.iter().all(|(p, l)| *p && *l);
+
+    for line in source.lines() {
+        let trimmed = line.trim_start();
+        if let Some(rest) = trimmed.strip_prefix("#import ") {
+            if active(&stack) {
+                let module = rest.trim().trim_matches('"');
+                splice(&dir.join(module), features, seen, out)?;
+            }
+            continue;
+        }
+        if let Some(flag) = trimmed.strip_prefix("#ifdef ") {
+            let parent = active(&stack);
+            let enabled = features.iter().any(|f| f.ident() == flag.trim());
+            stack.push((parent, enabled));
+            continue;
+        }
+        if trimmed == "#else" {
+            let (parent, local) = stack.pop().expect("#else without matching #ifdef");
+            stack.push((parent, !local));
+            continue;
+        }
+        if trimmed == "#endif" {
+            stack.pop().expect("#endif without matching #ifdef");
+            continue;
+        }
+        if active(&stack) {
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn write_temp(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "flame-preprocessor-test-{name}-{}.wgsl",
+            std::process::id()
+        ));
+        std::fs::File::create(&path)
+            .unwrap()
+            .write_all(contents.as_bytes())
+            .unwrap();
+        path
+    }
+
+    #[test]
+    fn ifdef_nesting_picks_the_enabled_branch() {
+        let path = write_temp(
+            "nesting",
+            "a\n#ifdef TONEMAP\nb\n#ifdef COLOR_PALETTE\nc\n#else\nd\n#endif\ne\n#endif\nf\n",
+        );
+        let out = preprocess(&path, &[ShaderFeature::Tonemap]).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(out, "a\nb\nd\ne\nf\n");
+    }
+
+    #[test]
+    fn ifdef_false_skips_the_block_and_takes_the_else_branch() {
+        let path = write_temp("disabled", "a\n#ifdef TONEMAP\nb\n#else\nc\n#endif\nd\n");
+        let out = preprocess(&path, &[]).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(out, "a\nc\nd\n");
+    }
+
+    #[test]
+    fn import_is_deduplicated_across_two_chains() {
+        let shared = write_temp("shared", "shared\n");
+        let shared_name = shared.file_name().unwrap().to_str().unwrap().to_string();
+        let a = write_temp("a", &format!("#import \"{shared_name}\"\na\n"));
+        let a_name = a.file_name().unwrap().to_str().unwrap().to_string();
+        let entry = write_temp(
+            "entry",
+            &format!("#import \"{shared_name}\"\n#import \"{a_name}\"\nentry\n"),
+        );
+
+        let out = preprocess(&entry, &[]).unwrap();
+
+        for path in [&shared, &a, &entry] {
+            std::fs::remove_file(path).unwrap();
+        }
+        assert_eq!(out, "shared\na\nentry\n");
+    }
+}
@@ -0,0 +1,45 @@
+use std::path::Path;
+use std::sync::mpsc::{channel, Receiver};
+
+/// Watches the `shaders/` directory (and anything `#import`ed from it) for
+/// changes so the pipeline can recompile and hot-swap without restarting the
+/// app. Polled once per frame rather than driven by the notify callback
+/// directly, so pipeline rebuilds stay on the render thread.
+pub struct ShaderWatcher {
+    _watcher: notify::RecommendedWatcher,
+    events: Receiver<notify::Result<notify::Event>>,
+}
+
+impl ShaderWatcher {
+    pub fn new(shader_dir: &Path) -> notify::Result<Self> {
+        use notify::Watcher;
+
+        let (tx, events) = channel();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            let _ = tx.send(event);
+        })?;
+        watcher.watch(shader_dir, notify::RecursiveMode::Recursive)?;
+
+        Ok(ShaderWatcher {
+            _watcher: watcher,
+            events,
+        })
+    }
+
+    /// Drains pending filesystem events and reports whether any shader file
+    /// was modified or created since the last call.
+    pub fn poll_changed(&self) -> bool {
+        let mut changed = false;
+        while let Ok(event) = self.events.try_recv() {
+            if let Ok(event) = event {
+                if matches!(
+                    event.kind,
+                    notify::EventKind::Modify(_) | notify::EventKind::Create(_)
+                ) {
+                    changed = true;
+                }
+            }
+        }
+        changed
+    }
+}